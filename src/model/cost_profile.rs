@@ -0,0 +1,39 @@
+use super::EdgeWeight;
+
+/// selectable set of per-edge-type weights used to shape the path finder's search, replacing the
+/// fixed `EdgeWeight::cost` constants so a caller can optimize for different objectives.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CostProfile {
+    /// the historical fixed per-edge-type weights (`EdgeWeight::cost`)
+    Balanced,
+    /// heavily penalizes `Board`/`Alight`, minimizing the number of transfers
+    MinimizeTransfers,
+    /// heavily penalizes `Walk`, minimizing time spent walking between stations
+    MinimizeWalking,
+    /// cost proportional to `get_duration()`, minimizing total travel time
+    MinimizeTravelTime,
+}
+
+impl CostProfile {
+    /// maps an edge to its cost under this profile
+    pub fn cost(&self, edge_weight: &EdgeWeight) -> u64 {
+        match self {
+            Self::Balanced => edge_weight.cost(),
+
+            Self::MinimizeTransfers => match edge_weight {
+                EdgeWeight::Board => 50,
+                EdgeWeight::Alight { .. } => 50,
+                _ => edge_weight.cost(),
+            },
+
+            Self::MinimizeWalking => match edge_weight {
+                EdgeWeight::Walk { .. } => 100,
+                _ => edge_weight.cost(),
+            },
+
+            // at least 1, so a zero-duration edge (e.g. MainArrivalRelation) still costs something
+            // and can't be traversed for free an unbounded number of times
+            Self::MinimizeTravelTime => edge_weight.get_duration().max(1),
+        }
+    }
+}
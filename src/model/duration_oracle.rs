@@ -0,0 +1,142 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufReader, BufWriter},
+    time::Instant,
+};
+
+use petgraph::graph::NodeIndex;
+use serde::{Deserialize, Serialize};
+
+use super::{path::Path, Model};
+
+/// precomputed minimum-remaining-duration tables, one per distinct destination station, so repeated
+/// searches that share a destination don't each re-run their own reverse Dijkstra. Doubles as the
+/// A*/beam heuristic and as a cheap "definitely unreachable in time" pre-check.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DurationOracle {
+    content_hash: u64,
+    tables: HashMap<String, HashMap<NodeIndex, u64>>,
+}
+
+impl DurationOracle {
+    /// builds a table for every distinct destination in `destinations`, keyed by station id
+    pub fn build(csv_folder_path: &str, model: &Model, destinations: &[String]) -> Self {
+        print!("building duration oracle for {} destination(s) ... ", destinations.len());
+        let start = Instant::now();
+
+        let mut tables = HashMap::with_capacity(destinations.len());
+
+        for destination in destinations.iter() {
+            if tables.contains_key(destination) {
+                continue;
+            }
+
+            let to = match model.find_end_node_index(destination) {
+                Some(to) => to,
+                None => continue,
+            };
+
+            tables.insert(destination.clone(), Path::build_heuristic(&model.graph, to));
+        }
+
+        println!("done ({}ms)", start.elapsed().as_millis());
+
+        Self {
+            content_hash: Self::content_hash(csv_folder_path),
+            tables,
+        }
+    }
+
+    /// minimum remaining duration from `node` to `destination`, if both are known
+    pub fn remaining_duration(&self, destination: &str, node: NodeIndex) -> Option<u64> {
+        self.tables.get(destination)?.get(&node).copied()
+    }
+
+    /// table for a single destination, used directly as the A*/beam heuristic
+    pub fn table(&self, destination: &str) -> Option<&HashMap<NodeIndex, u64>> {
+        self.tables.get(destination)
+    }
+
+    pub fn save_to_file(&self, filepath: &str) {
+        print!("saving duration oracle to {} ... ", filepath);
+        let start = Instant::now();
+
+        let writer = BufWriter::new(
+            File::create(&format!("{}durations.bincode", filepath))
+                .expect(&format!("Could not open file {}durations.bincode", filepath)),
+        );
+        bincode::serialize_into(writer, self).expect("Could not save duration oracle to file");
+
+        println!("done ({}ms)", start.elapsed().as_millis());
+    }
+
+    /// loads the oracle at `filepath`, returning `None` (instead of a stale table) if the file is
+    /// missing or its stored content hash no longer matches the source CSVs at `csv_folder_path`
+    pub fn load_from_file(filepath: &str, csv_folder_path: &str) -> Option<Self> {
+        let file = File::open(&format!("{}durations.bincode", filepath)).ok()?;
+        let oracle: Self = bincode::deserialize_from(BufReader::new(file)).ok()?;
+
+        if oracle.content_hash != Self::content_hash(csv_folder_path) {
+            return None;
+        }
+
+        Some(oracle)
+    }
+
+    /// loads a cached oracle for `destinations` if it is still valid, otherwise rebuilds and persists it
+    pub fn load_or_build(filepath: &str, csv_folder_path: &str, model: &Model, destinations: &[String]) -> Self {
+        match Self::load_from_file(filepath, csv_folder_path) {
+            Some(oracle) => oracle,
+            None => {
+                let oracle = Self::build(csv_folder_path, model, destinations);
+                oracle.save_to_file(filepath);
+                oracle
+            }
+        }
+    }
+
+    /// hashes the concatenated contents of the source CSVs, the same way `Model::csv_content_hash`
+    /// does for the graph cache, so any change to the underlying timetable data - not just a change
+    /// to how many trips/stations exist - invalidates a previously persisted oracle
+    fn content_hash(csv_folder_path: &str) -> u64 {
+        Model::csv_content_hash(csv_folder_path)
+    }
+}
+
+#[cfg(test)]
+mod content_hash_tests {
+    use std::io::Write;
+
+    use super::*;
+
+    /// returns a `csv_folder_path` (trailing slash included, as every caller expects) pointing at a
+    /// freshly written folder of the three source CSVs
+    fn write_csv_folder(name: &str, trips_contents: &str) -> String {
+        let folder = std::env::temp_dir().join(format!("duration_oracle_test_{}_{}", std::process::id(), name));
+        std::fs::create_dir_all(&folder).unwrap();
+
+        std::fs::File::create(folder.join("footpaths.csv")).unwrap().write_all(b"from_station,to_station,duration\n").unwrap();
+        std::fs::File::create(folder.join("stations.csv")).unwrap().write_all(b"id,transfer_time\n").unwrap();
+        std::fs::File::create(folder.join("trips.csv")).unwrap().write_all(trips_contents.as_bytes()).unwrap();
+
+        format!("{}/", folder.to_str().unwrap())
+    }
+
+    /// a stale oracle must be rejected once `trips.csv` changes, even when the number of trips and
+    /// stations stays the same - the old structural hash (node/edge count + station ids) couldn't
+    /// tell an edited departure time or capacity from an unedited one
+    #[test]
+    fn invalidates_when_trip_departure_time_changes() {
+        let folder_before = write_csv_folder("before", "id,from_station,to_station,departure,arrival,capacity\n1,A,B,100,200,50\n");
+        let folder_after = write_csv_folder("after", "id,from_station,to_station,departure,arrival,capacity\n1,A,B,999,200,50\n");
+
+        let hash_before = DurationOracle::content_hash(&folder_before);
+        let hash_after = DurationOracle::content_hash(&folder_after);
+
+        assert_ne!(hash_before, hash_after);
+
+        std::fs::remove_dir_all(folder_before.trim_end_matches('/')).ok();
+        std::fs::remove_dir_all(folder_after.trim_end_matches('/')).ok();
+    }
+}
@@ -3,7 +3,10 @@ use std::{cmp::max, collections::HashMap, fs::File, io::{BufReader, BufWriter},
 
 use colored::Colorize;
 
-use super::{Model, path::{self, Path}, trip::Trip};
+use petgraph::graph::NodeIndex;
+use rayon::{prelude::*, ThreadPoolBuilder};
+
+use super::{Model, path::{self, Path, SearchMode}, duration_oracle::DurationOracle, trip::Trip};
 
 /// travel group
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -23,11 +26,19 @@ pub struct Group {
     // Wenn der Wert nicht leer ist, gibt er die Trip ID (Integer) der Fahrt an, in der sich die Gruppe befindet.
     pub in_trip: Option<usize>,
 
+    // optional mandatory intermediate stations (e.g. forced connections, crew constraints) the
+    // group's route must pass through, in no particular order - `find_solutions` picks the
+    // cheapest feasible visiting order, see `path_finder::search_route_with_waypoints`
+    pub via_stations: Vec<String>,
+
     pub paths: Vec<Path>, // possible paths for this group
 }
 
 impl Group {
 
+    /// upper bound for the beam-width doubling fallback in `search_paths`
+    const MAX_BEAM_WIDTH: usize = 4096;
+
     pub fn from_maps_to_vec(group_maps: &Vec<HashMap<String, String>>, trips: &HashMap<String, Trip>) -> Vec<Self> {
         println!("parsing {} group(s)", group_maps.len());
 
@@ -48,6 +59,12 @@ impl Group {
                 Some(in_trip_value.parse().unwrap())
             };
 
+            // optional column: absent in older group CSVs, empty when a group has no via-constraint
+            let via_stations = group_map
+                .get("via_stations")
+                .map(|value| value.split(';').filter(|station_id| !station_id.is_empty()).map(String::from).collect())
+                .unwrap_or_default();
+
             groups.push(Self {
                 id,
                 start: group_map.get("start").unwrap().clone(),
@@ -56,6 +73,7 @@ impl Group {
                 arrival: group_map.get("arrival").unwrap().parse().unwrap(),
                 passengers: group_map.get("passengers").unwrap().parse().unwrap(),
                 in_trip,
+                via_stations,
                 paths: Vec::new(),
             });
         }
@@ -95,11 +113,14 @@ impl Group {
         groups
     }
 
-    /// returns (remaining_duration, path), returns true if there was at least one path found
-    pub fn search_paths(&mut self, model: &Model, budget_steps: &[u64], duration_factor: f64) -> bool {
-        let from = model
-            .find_start_node_index(&self.start, self.departure)
-            .expect("Could not find departure at from_station");
+    /// fills `self.paths` (best first), returns true if there was at least one path found.
+    /// `beam_width`, if set, bounds the search to a beam of that width, doubling it (up to
+    /// `MAX_BEAM_WIDTH`) and retrying whenever the beam collapses before reaching the destination.
+    /// `oracle`, if given, supplies the precomputed remaining-duration table for `self.destination`
+    /// instead of recomputing a reverse Dijkstra for every group, and lets a doomed search bail out
+    /// immediately.
+    pub fn search_paths(&mut self, model: &Model, mode: SearchMode, budget_steps: &[u64], duration_factor: f64, beam_width: Option<usize>, oracle: Option<&DurationOracle>) -> bool {
+        let from = self.find_source_node_index(model);
         let to = model
             .find_end_node_index(&self.destination)
             .expect("Could not find destination station");
@@ -111,9 +132,18 @@ impl Group {
 
         // max duration should depend on the original travel time
         let travel_time = self.arrival - self.departure;
-        
-        //let max_duration = (travel_time as f64 * duration_factor) as u64; // todo: factor to modify later if not a path could be found for all groups
-        let max_duration = Group::calculate_max_travel_duration(travel_time);
+        let max_duration = (travel_time as f64 * duration_factor) as u64;
+
+        // if even the oracle's lower bound can't make it in time, the search is doomed - skip it
+        if let Some(oracle) = oracle {
+            if let Some(min_remaining) = oracle.remaining_duration(&self.destination, from) {
+                if min_remaining > max_duration {
+                    self.paths = Vec::new();
+                    println!("{} -> {} unreachable in time (oracle lower bound)", self.start, self.destination);
+                    return false
+                }
+            }
+        }
 
         let start = Instant::now();
         print!(
@@ -129,14 +159,38 @@ impl Group {
         //     max_duration,
         //     max_budget // initial budget for cost (each edge has individual search cost)
         // );
-        self.paths = path::Path::all_paths_iddfs(
-            &model.graph,
-            from,
-            to,
-            self.passengers as u64,
-            max_duration,
-            budget_steps,
-        );
+        let heuristic = |model: &Model, to: NodeIndex| match oracle.and_then(|oracle| oracle.table(&self.destination)) {
+            Some(table) => table.clone(),
+            None => path::Path::build_heuristic(&model.graph, to),
+        };
+
+        self.paths = match beam_width {
+            Some(initial_beam_width) => {
+                let h = heuristic(model, to);
+
+                let mut width = initial_beam_width;
+                let mut paths = Vec::new();
+
+                while paths.is_empty() && (width == initial_beam_width || width <= Self::MAX_BEAM_WIDTH) {
+                    paths = path::Path::search_beam(&model.graph, from, to, self.passengers as u64, max_duration, width, &h);
+
+                    if paths.is_empty() {
+                        width *= 2;
+                    }
+                }
+
+                paths
+            }
+            None => match mode {
+                SearchMode::Iddfs => path::Path::all_paths_iddfs(&model.graph, from, to, self.passengers as u64, max_duration, budget_steps),
+                SearchMode::AStar => {
+                    path::Path::search_astar(&model.graph, from, to, self.passengers as u64, max_duration, &heuristic(model, to)).into_iter().collect()
+                }
+                SearchMode::GreedyBestFirst => {
+                    path::Path::search_greedy_best_first(&model.graph, from, to, self.passengers as u64, max_duration, &heuristic(model, to)).into_iter().collect()
+                }
+            },
+        };
 
         print!("done in {}ms, ", start.elapsed().as_millis());
 
@@ -162,7 +216,146 @@ impl Group {
         }
     }
 
-    fn calculate_max_travel_duration(travel_time: u64) -> u64 {
-        2 * travel_time + 50
+    /// if the group is already riding a trip (`in_trip` set), it can only start from that trip's
+    /// arrival node (or later, by staying aboard) - starting it at the station instead would force
+    /// it through the station transfer time to re-board a trip it's already on. Otherwise it starts
+    /// waiting at its station.
+    fn find_source_node_index(&self, model: &Model) -> NodeIndex {
+        match self.in_trip {
+            Some(trip_id) => model
+                .find_trip_arrival_node_index(trip_id)
+                .expect("Could not find arrival node for in_trip"),
+            None => model
+                .find_start_node_index(&self.start, self.departure)
+                .expect("Could not find departure at from_station"),
+        }
+    }
+
+    /// runs `search_paths` for every group concurrently on a rayon thread pool, since each search
+    /// only reads the immutable `model`/graph and mutates its own group's `paths`. Falls back to
+    /// rayon's default thread count when `num_threads` is `None`.
+    pub fn search_all_paths(
+        groups: &mut [Group],
+        model: &Model,
+        mode: SearchMode,
+        budget_steps: &[u64],
+        duration_factor: f64,
+        beam_width: Option<usize>,
+        oracle: Option<&DurationOracle>,
+        num_threads: Option<usize>,
+    ) {
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(num_threads.unwrap_or(0)) // 0 lets rayon pick the default (num_cpus)
+            .build()
+            .expect("Could not build rayon thread pool");
+
+        let start = Instant::now();
+
+        pool.install(|| {
+            groups.par_iter_mut().for_each(|group| {
+                group.search_paths(model, mode, budget_steps, duration_factor, beam_width, oracle);
+            });
+        });
+
+        let groups_without_path = groups.iter().filter(|group| group.paths.is_empty()).count();
+        let total_paths_found: usize = groups.iter().map(|group| group.paths.len()).sum();
+
+        println!(
+            "{}",
+            format!(
+                "searched paths for {} group(s) in {}ms: {} path(s) found, {} group(s) without a path",
+                groups.len(),
+                start.elapsed().as_millis(),
+                total_paths_found,
+                groups_without_path
+            )
+            .blue()
+        );
+    }
+
+    /// relaxation factor multiplier applied to groups without a path after each round
+    const RELAXATION_FACTOR_MULTIPLIER: f64 = 1.5;
+
+    /// replaces the hardcoded `2 * travel_time + 50` with a principled, data-driven relaxation:
+    /// starts every group at `max_duration = travel_time * initial_factor`, searches all of them in
+    /// parallel, then multiplicatively relaxes (up to `max_factor`) and retries only the groups that
+    /// found no path, repeating until every group has at least one path or `max_factor` is reached.
+    /// Returns, per group id, the factor that finally succeeded (or the capped `max_factor` for
+    /// groups that never found a path).
+    pub fn search_all_paths_adaptive(
+        groups: &mut [Group],
+        model: &Model,
+        mode: SearchMode,
+        budget_steps: &[u64],
+        initial_factor: f64,
+        max_factor: f64,
+        beam_width: Option<usize>,
+        oracle: Option<&DurationOracle>,
+        num_threads: Option<usize>,
+    ) -> HashMap<u64, f64> {
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(num_threads.unwrap_or(0))
+            .build()
+            .expect("Could not build rayon thread pool");
+
+        let mut factors: HashMap<u64, f64> = groups.iter().map(|group| (group.id, initial_factor)).collect();
+        let mut round = 0;
+
+        loop {
+            let pending_count = groups.iter().filter(|group| group.paths.is_empty()).count();
+            if pending_count == 0 {
+                break;
+            }
+
+            round += 1;
+            let start = Instant::now();
+
+            pool.install(|| {
+                groups
+                    .par_iter_mut()
+                    .filter(|group| group.paths.is_empty())
+                    .for_each(|group| {
+                        let factor = factors[&group.id];
+                        group.search_paths(model, mode, budget_steps, factor, beam_width, oracle);
+                    });
+            });
+
+            let still_pending = groups.iter().filter(|group| group.paths.is_empty()).count();
+
+            println!(
+                "{}",
+                format!(
+                    "relaxation round {}: searched {} group(s) in {}ms, {} still without a path",
+                    round,
+                    pending_count,
+                    start.elapsed().as_millis(),
+                    still_pending
+                )
+                .blue()
+            );
+
+            if still_pending == 0 {
+                break;
+            }
+
+            let mut any_relaxed = false;
+
+            for group in groups.iter() {
+                if group.paths.is_empty() {
+                    let factor = factors.get_mut(&group.id).unwrap();
+                    if *factor < max_factor {
+                        *factor = (*factor * Self::RELAXATION_FACTOR_MULTIPLIER).min(max_factor);
+                        any_relaxed = true;
+                    }
+                }
+            }
+
+            // no group's factor could be relaxed any further - stop, these groups are unroutable
+            if !any_relaxed {
+                break;
+            }
+        }
+
+        factors
     }
 }
\ No newline at end of file
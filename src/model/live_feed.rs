@@ -0,0 +1,281 @@
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::BufReader;
+
+use petgraph::EdgeDirection::Incoming;
+use serde::Deserialize;
+
+use super::{EdgeWeight, Model, NodeWeight};
+
+/// status of a single stop as reported by a live on-board/portal feed
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StopStatus {
+    Departed,
+    Future,
+    Cancelled,
+}
+
+/// a single stop of a live trip report, carrying both the scheduled and the actual/delayed time
+#[derive(Debug, Deserialize)]
+pub struct LiveStop {
+    pub station_id: String,
+    pub scheduled_time: u64,
+    pub actual_time: u64,
+    pub status: StopStatus,
+}
+
+/// a live on-board/portal trip report: the ordered stops of one running trip
+#[derive(Debug, Deserialize)]
+pub struct LiveTrip {
+    pub trip_id: u64,
+    pub stops: Vec<LiveStop>,
+}
+
+impl Model {
+    /// reads a live delay/cancellation feed and patches `self.graph` in place: shifts affected
+    /// `NodeWeight::{Departure, Arrival}` times and `EdgeWeight::Ride`/`WaitInTrain` durations by
+    /// the reported delay, and zero-capacities `Ride` edges of cancelled trips so the path finder
+    /// can no longer route passengers onto them. Every delayed `Departure` also shifts its own
+    /// dedicated `NodeWeight::Transfer` node (linked by `EdgeWeight::Board`) by the same delay, and
+    /// `self.stations_departures` is re-sorted for every station touched, so a station-origin
+    /// group's `find_start_node_index` lookup sees the delay too, not just an `in_trip` group's
+    /// direct arrival/departure node references.
+    pub fn apply_live_feed(&mut self, live_feed_path: &str) {
+        print!("applying live feed {} ... ", live_feed_path);
+
+        let reader = BufReader::new(
+            File::open(live_feed_path).expect(&format!("Could not open live feed file {}", live_feed_path)),
+        );
+        let live_trips: Vec<LiveTrip> =
+            serde_json::from_reader(reader).expect("Could not parse live feed file");
+
+        let mut patched_trips = 0;
+        let mut cancelled_trips = 0;
+
+        for live_trip in live_trips.iter() {
+            let cancelled = live_trip.stops.iter().any(|stop| stop.status == StopStatus::Cancelled);
+
+            let mut affected_stations: HashSet<String> = HashSet::new();
+
+            for node_index in self.graph.node_indices().collect::<Vec<_>>() {
+                let (delay, is_departure) = match self.graph.node_weight(node_index).unwrap() {
+                    NodeWeight::Departure { trip_id, station_id, .. } if *trip_id == live_trip.trip_id => {
+                        (Self::delay_for_station(live_trip, station_id), true)
+                    }
+                    NodeWeight::Arrival { trip_id, station_id, .. } if *trip_id == live_trip.trip_id => {
+                        (Self::delay_for_station(live_trip, station_id), false)
+                    }
+                    _ => (None, false),
+                };
+
+                if let Some(delay) = delay {
+                    if delay != 0 {
+                        match self.graph.node_weight_mut(node_index).unwrap() {
+                            NodeWeight::Departure { time, .. } => *time += delay,
+                            NodeWeight::Arrival { time, .. } => *time += delay,
+                            _ => {}
+                        }
+
+                        if is_departure {
+                            // every departure has exactly one dedicated Transfer node, reached via
+                            // its incoming Board edge - shift it by the same delay so boarding
+                            // passengers still see a consistent (transfer_time, departure_time) pair
+                            let mut walker = self.graph.neighbors_directed(node_index, Incoming).detach();
+
+                            while let Some((edge_index, transfer_node_index)) = walker.next(&self.graph) {
+                                if !matches!(self.graph.edge_weight(edge_index).unwrap(), EdgeWeight::Board) {
+                                    continue;
+                                }
+
+                                if let NodeWeight::Transfer { time, station_id } = self.graph.node_weight_mut(transfer_node_index).unwrap() {
+                                    *time += delay;
+                                    affected_stations.insert(station_id.clone());
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+
+            // the shifted transfer nodes may now sort differently relative to the rest of their
+            // station's transfers - resync self.stations_departures with each transfer node's
+            // current (post-shift) time and re-sort, so find_start_node_index keeps seeing a
+            // time-ordered list
+            for station_id in affected_stations {
+                if let Some(station_departures) = self.stations_departures.get_mut(&station_id) {
+                    for (cached_time, transfer_node_index) in station_departures.iter_mut() {
+                        *cached_time = self.graph.node_weight(*transfer_node_index).unwrap().get_time().unwrap();
+                    }
+
+                    station_departures.sort_unstable_by_key(|(time, _)| *time);
+                }
+            }
+
+            // the Ride/WaitInTrain edges cache a duration derived from their endpoint times, so any
+            // shifted node invalidates it - recompute from the (now patched) node times
+            for edge_index in self.graph.edge_indices().collect::<Vec<_>>() {
+                let (from, to) = self.graph.edge_endpoints(edge_index).unwrap();
+                let from_weight = self.graph.node_weight(from).unwrap();
+                let to_weight = self.graph.node_weight(to).unwrap();
+
+                let belongs_to_trip = matches!(from_weight, NodeWeight::Departure { trip_id, .. } if *trip_id == live_trip.trip_id)
+                    || matches!(from_weight, NodeWeight::Arrival { trip_id, .. } if *trip_id == live_trip.trip_id)
+                    || matches!(to_weight, NodeWeight::Departure { trip_id, .. } if *trip_id == live_trip.trip_id)
+                    || matches!(to_weight, NodeWeight::Arrival { trip_id, .. } if *trip_id == live_trip.trip_id);
+
+                if !belongs_to_trip {
+                    continue;
+                }
+
+                let from_time = from_weight.get_time();
+                let to_time = to_weight.get_time();
+
+                if let (Some(from_time), Some(to_time)) = (from_time, to_time) {
+                    match self.graph.edge_weight_mut(edge_index).unwrap() {
+                        EdgeWeight::Ride { duration, .. } => *duration = to_time.saturating_sub(from_time),
+                        EdgeWeight::WaitInTrain { duration } => *duration = to_time.saturating_sub(from_time),
+                        _ => {}
+                    }
+                }
+            }
+
+            if cancelled {
+                for edge_index in self.graph.edge_indices().collect::<Vec<_>>() {
+                    let (from, to) = self.graph.edge_endpoints(edge_index).unwrap();
+                    let belongs_to_trip = match (self.graph.node_weight(from).unwrap(), self.graph.node_weight(to).unwrap()) {
+                        (NodeWeight::Departure { trip_id: a, .. }, NodeWeight::Arrival { trip_id: b, .. }) => {
+                            *a == live_trip.trip_id && *b == live_trip.trip_id
+                        }
+                        _ => false,
+                    };
+
+                    if belongs_to_trip {
+                        // zero utilization too, not just capacity: get_remaining_capacity() computes
+                        // capacity - utilization with plain u64 subtraction, so leaving a
+                        // pre-existing utilization in place on a now-zero-capacity edge would
+                        // underflow - panicking in debug, and in release wrapping to near-u64::MAX,
+                        // making a cancelled edge look like it has virtually unlimited capacity
+                        if let EdgeWeight::Ride { capacity, utilization, .. } = self.graph.edge_weight_mut(edge_index).unwrap() {
+                            *capacity = 0;
+                            *utilization = 0;
+                        }
+                    }
+                }
+
+                cancelled_trips += 1;
+            } else {
+                patched_trips += 1;
+            }
+        }
+
+        println!("done, patched {} trip(s), cancelled {} trip(s)", patched_trips, cancelled_trips);
+    }
+
+    fn delay_for_station(live_trip: &LiveTrip, station_id: &str) -> Option<u64> {
+        live_trip
+            .stops
+            .iter()
+            .find(|stop| stop.station_id == station_id)
+            .map(|stop| stop.actual_time.saturating_sub(stop.scheduled_time))
+    }
+}
+
+#[cfg(test)]
+mod apply_live_feed_tests {
+    use std::collections::HashMap;
+    use std::io::Write;
+
+    use petgraph::graph::DiGraph;
+
+    use super::*;
+
+    fn empty_model() -> Model {
+        Model {
+            graph: DiGraph::new(),
+            stations_departures: HashMap::new(),
+            station_arrival_main_node_indices: HashMap::new(),
+        }
+    }
+
+    fn write_live_feed(contents: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("live_feed_test_{}_{}.json", std::process::id(), contents.len()));
+        std::fs::File::create(&path).unwrap().write_all(contents.as_bytes()).unwrap();
+        path
+    }
+
+    /// a cancelled trip's `Ride` edge must end up with capacity == utilization == 0, never an
+    /// underflowing `capacity - utilization` - the bug this test locks in had a pre-utilized edge
+    /// left with `utilization > capacity == 0` after cancellation, which panics in debug and wraps
+    /// to near-`u64::MAX` in release when `get_remaining_capacity()` subtracts them.
+    #[test]
+    fn cancelling_a_pre_utilized_trip_zeroes_utilization_too() {
+        let mut model = empty_model();
+
+        let departure = model.graph.add_node(NodeWeight::Departure { trip_id: 1, time: 100, station_id: "A".into() });
+        let arrival = model.graph.add_node(NodeWeight::Arrival { trip_id: 1, time: 200, station_id: "B".into() });
+        let ride_edge = model.graph.add_edge(departure, arrival, EdgeWeight::Ride { duration: 100, capacity: 50, utilization: 30 });
+
+        let feed_path = write_live_feed(r#"[
+            {
+                "trip_id": 1,
+                "stops": [
+                    { "station_id": "A", "scheduled_time": 100, "actual_time": 100, "status": "cancelled" },
+                    { "station_id": "B", "scheduled_time": 200, "actual_time": 200, "status": "cancelled" }
+                ]
+            }
+        ]"#);
+
+        model.apply_live_feed(feed_path.to_str().unwrap());
+        std::fs::remove_file(&feed_path).ok();
+
+        let edge_weight = model.graph.edge_weight(ride_edge).unwrap();
+        assert_eq!(edge_weight.get_capacity(), 0);
+        assert_eq!(edge_weight.get_utilization(), 0);
+        assert_eq!(edge_weight.get_remaining_capacity(), 0); // must not underflow
+    }
+
+    /// a delayed departure must shift its own `Transfer` node by the same delay and resync/re-sort
+    /// `stations_departures` for the station it belongs to - including flipping its order relative
+    /// to an unaffected transfer that used to sort earlier but now sorts later
+    #[test]
+    fn delaying_a_departure_reshifts_and_resorts_its_transfer_node() {
+        let mut model = empty_model();
+
+        let transfer_delayed = model.graph.add_node(NodeWeight::Transfer { time: 100, station_id: "A".into() });
+        let transfer_unaffected = model.graph.add_node(NodeWeight::Transfer { time: 120, station_id: "A".into() });
+        let departure = model.graph.add_node(NodeWeight::Departure { trip_id: 1, time: 100, station_id: "A".into() });
+        let arrival = model.graph.add_node(NodeWeight::Arrival { trip_id: 1, time: 200, station_id: "B".into() });
+
+        model.graph.add_edge(transfer_delayed, departure, EdgeWeight::Board);
+        model.graph.add_edge(departure, arrival, EdgeWeight::Ride { duration: 100, capacity: 50, utilization: 0 });
+
+        model.stations_departures.insert("A".to_string(), vec![(100, transfer_delayed), (120, transfer_unaffected)]);
+
+        let feed_path = write_live_feed(r#"[
+            {
+                "trip_id": 1,
+                "stops": [
+                    { "station_id": "A", "scheduled_time": 100, "actual_time": 150, "status": "future" },
+                    { "station_id": "B", "scheduled_time": 200, "actual_time": 250, "status": "future" }
+                ]
+            }
+        ]"#);
+
+        model.apply_live_feed(feed_path.to_str().unwrap());
+        std::fs::remove_file(&feed_path).ok();
+
+        // the delayed transfer node itself moved to 150
+        assert_eq!(model.graph.node_weight(transfer_delayed).unwrap().get_time(), Some(150));
+        // the unrelated transfer node at the same station is untouched
+        assert_eq!(model.graph.node_weight(transfer_unaffected).unwrap().get_time(), Some(120));
+
+        // stations_departures must be resynced to the new times and re-sorted - transfer_unaffected
+        // (120) now sorts before transfer_delayed (150), the opposite of their original order
+        let station_departures = model.stations_departures.get("A").unwrap();
+        assert_eq!(station_departures, &vec![(120, transfer_unaffected), (150, transfer_delayed)]);
+
+        // a group departing at 130 must now be routed onto the delayed transfer, not miss it
+        assert_eq!(model.find_start_node_index("A", 130), Some(transfer_delayed));
+    }
+}
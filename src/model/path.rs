@@ -0,0 +1,375 @@
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashMap},
+};
+
+use petgraph::{graph::{DiGraph, EdgeIndex, NodeIndex}, EdgeDirection::Incoming, visit::EdgeRef};
+
+use super::{EdgeWeight, NodeWeight};
+
+/// selectable search strategy for `Path::search_paths`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchMode {
+    /// iterative-deepening DFS, budget-limited (the original behaviour)
+    Iddfs,
+    /// A* guided by an admissible remaining-travel-time heuristic, returns the time-optimal path
+    AStar,
+    /// same heuristic as `AStar`, but the frontier is ordered by `h` alone for a fast, non-optimal path
+    GreedyBestFirst,
+}
+
+/// a single candidate route for a group through the timetable graph
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Path {
+    edges: Vec<EdgeIndex>,
+    duration: u64,
+    remaining_budget: u64,
+}
+
+impl Path {
+    pub fn duration(&self) -> u64 {
+        self.duration
+    }
+
+    pub fn len(&self) -> usize {
+        self.edges.len()
+    }
+
+    pub fn edges(&self) -> &[EdgeIndex] {
+        &self.edges
+    }
+}
+
+// order by remaining_budget so `paths.sort_unstable(); paths.reverse()` puts the best path first
+impl PartialOrd for Path {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Path {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.remaining_budget.cmp(&other.remaining_budget)
+    }
+}
+
+/// frontier entry for the A*/greedy heap, ordered so `BinaryHeap` (a max-heap) pops the smallest `f`/`h` first
+#[derive(Debug, Clone)]
+struct Frontier {
+    node: NodeIndex,
+    edges: Vec<EdgeIndex>,
+    elapsed_duration: u64,
+    f: u64,
+}
+
+impl PartialEq for Frontier {
+    fn eq(&self, other: &Self) -> bool {
+        self.f == other.f
+    }
+}
+impl Eq for Frontier {}
+
+impl PartialOrd for Frontier {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Frontier {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // reversed, so BinaryHeap (max-heap) pops the lowest f first
+        other.f.cmp(&self.f)
+    }
+}
+
+impl Path {
+    /// iterative-deepening DFS bounded by `budget_steps`, the original search mode
+    pub fn all_paths_iddfs(
+        graph: &DiGraph<NodeWeight, EdgeWeight>,
+        from: NodeIndex,
+        to: NodeIndex,
+        passengers: u64,
+        max_duration: u64,
+        budget_steps: &[u64],
+    ) -> Vec<Self> {
+        let mut paths = Vec::new();
+
+        for budget in budget_steps.iter() {
+            Self::iddfs_step(graph, from, to, passengers, max_duration, *budget, &mut Vec::new(), 0, 0, &mut paths);
+
+            if !paths.is_empty() {
+                break;
+            }
+        }
+
+        paths
+    }
+
+    fn iddfs_step(
+        graph: &DiGraph<NodeWeight, EdgeWeight>,
+        current: NodeIndex,
+        to: NodeIndex,
+        passengers: u64,
+        max_duration: u64,
+        budget: u64,
+        edges: &mut Vec<EdgeIndex>,
+        duration: u64,
+        cost: u64,
+        paths: &mut Vec<Self>,
+    ) {
+        if current == to {
+            paths.push(Self {
+                edges: edges.clone(),
+                duration,
+                remaining_budget: budget.saturating_sub(cost),
+            });
+            return;
+        }
+
+        for edge_ref in graph.edges(current) {
+            let edge_weight = edge_ref.weight();
+
+            if edge_weight.get_remaining_capacity() < passengers {
+                continue;
+            }
+
+            let next_duration = duration + edge_weight.get_duration();
+            let next_cost = cost + edge_weight.cost();
+
+            if next_duration > max_duration || next_cost > budget {
+                continue;
+            }
+
+            edges.push(edge_ref.id());
+            Self::iddfs_step(graph, edge_ref.target(), to, passengers, max_duration, budget, edges, next_duration, next_cost, paths);
+            edges.pop();
+        }
+    }
+
+    /// A* search toward `to`, guided by `h` (an admissible lower bound on remaining travel time).
+    /// The first path popped that reaches `to` is time-optimal, since `h` never overestimates.
+    pub fn search_astar(
+        graph: &DiGraph<NodeWeight, EdgeWeight>,
+        from: NodeIndex,
+        to: NodeIndex,
+        passengers: u64,
+        max_duration: u64,
+        h: &HashMap<NodeIndex, u64>,
+    ) -> Option<Self> {
+        Self::search_heap(graph, from, to, passengers, max_duration, h, false)
+    }
+
+    /// greedy best-first search toward `to`, ordered by `h` alone for a fast, non-optimal path
+    pub fn search_greedy_best_first(
+        graph: &DiGraph<NodeWeight, EdgeWeight>,
+        from: NodeIndex,
+        to: NodeIndex,
+        passengers: u64,
+        max_duration: u64,
+        h: &HashMap<NodeIndex, u64>,
+    ) -> Option<Self> {
+        Self::search_heap(graph, from, to, passengers, max_duration, h, true)
+    }
+
+    fn search_heap(
+        graph: &DiGraph<NodeWeight, EdgeWeight>,
+        from: NodeIndex,
+        to: NodeIndex,
+        passengers: u64,
+        max_duration: u64,
+        h: &HashMap<NodeIndex, u64>,
+        greedy: bool,
+    ) -> Option<Self> {
+        let heuristic = |node: NodeIndex| h.get(&node).copied().unwrap_or(0);
+
+        let mut heap = BinaryHeap::new();
+        heap.push(Frontier {
+            node: from,
+            edges: Vec::new(),
+            elapsed_duration: 0,
+            f: heuristic(from),
+        });
+
+        let mut best_elapsed: HashMap<NodeIndex, u64> = HashMap::new();
+
+        while let Some(current) = heap.pop() {
+            if current.node == to {
+                return Some(Self {
+                    duration: current.elapsed_duration,
+                    remaining_budget: max_duration.saturating_sub(current.elapsed_duration),
+                    edges: current.edges,
+                });
+            }
+
+            if let Some(&seen) = best_elapsed.get(&current.node) {
+                if seen <= current.elapsed_duration {
+                    continue;
+                }
+            }
+            best_elapsed.insert(current.node, current.elapsed_duration);
+
+            for edge_ref in graph.edges(current.node) {
+                let edge_weight = edge_ref.weight();
+
+                if edge_weight.get_remaining_capacity() < passengers {
+                    continue;
+                }
+
+                let elapsed_duration = current.elapsed_duration + edge_weight.get_duration();
+                let f = if greedy {
+                    heuristic(edge_ref.target())
+                } else {
+                    elapsed_duration + heuristic(edge_ref.target())
+                };
+
+                // prune any frontier entry whose f exceeds max_duration
+                if !greedy && f > max_duration {
+                    continue;
+                }
+                if elapsed_duration > max_duration {
+                    continue;
+                }
+
+                let mut edges = current.edges.clone();
+                edges.push(edge_ref.id());
+
+                heap.push(Frontier {
+                    node: edge_ref.target(),
+                    edges,
+                    elapsed_duration,
+                    f,
+                });
+            }
+        }
+
+        None
+    }
+
+    /// lower bound on remaining travel time from every node to `to`, computed once by a reverse
+    /// Dijkstra over the *minimum* traversal duration of each edge and ignoring capacity. This
+    /// relaxation guarantees the bound never overestimates the real travel time, so it is safe
+    /// to use both as the A*/greedy heuristic and as an early-reject test.
+    pub fn build_heuristic(graph: &DiGraph<NodeWeight, EdgeWeight>, to: NodeIndex) -> HashMap<NodeIndex, u64> {
+        let mut h = HashMap::new();
+        h.insert(to, 0);
+
+        let mut heap = BinaryHeap::new();
+        heap.push(Frontier { node: to, edges: Vec::new(), elapsed_duration: 0, f: 0 });
+
+        while let Some(current) = heap.pop() {
+            if let Some(&best) = h.get(&current.node) {
+                if best < current.elapsed_duration {
+                    continue;
+                }
+            }
+
+            for edge_ref in graph.edges_directed(current.node, Incoming) {
+                let predecessor = edge_ref.source();
+                let candidate = current.elapsed_duration + edge_ref.weight().get_duration();
+
+                let improved = match h.get(&predecessor) {
+                    Some(&existing) => candidate < existing,
+                    None => true,
+                };
+
+                if improved {
+                    h.insert(predecessor, candidate);
+                    heap.push(Frontier { node: predecessor, edges: Vec::new(), elapsed_duration: candidate, f: candidate });
+                }
+            }
+        }
+
+        h
+    }
+
+    /// dispatches to the selected `SearchMode`, returning every found path for `Iddfs` (to preserve
+    /// the existing best-of-budget_steps behaviour) or at most one path for `AStar`/`GreedyBestFirst`
+    pub fn search_paths(
+        mode: SearchMode,
+        graph: &DiGraph<NodeWeight, EdgeWeight>,
+        from: NodeIndex,
+        to: NodeIndex,
+        passengers: u64,
+        max_duration: u64,
+        budget_steps: &[u64],
+    ) -> Vec<Self> {
+        match mode {
+            SearchMode::Iddfs => Self::all_paths_iddfs(graph, from, to, passengers, max_duration, budget_steps),
+            SearchMode::AStar => {
+                let h = Self::build_heuristic(graph, to);
+                Self::search_astar(graph, from, to, passengers, max_duration, &h).into_iter().collect()
+            }
+            SearchMode::GreedyBestFirst => {
+                let h = Self::build_heuristic(graph, to);
+                Self::search_greedy_best_first(graph, from, to, passengers, max_duration, &h).into_iter().collect()
+            }
+        }
+    }
+
+    /// beam-search: expands the frontier layer by layer, keeping only the top `beam_width` partial
+    /// paths ranked by `f = elapsed_duration + h` after each expansion step and discarding the rest.
+    /// Caps memory at O(beam_width * max_depth) at the cost of completeness.
+    pub fn search_beam(
+        graph: &DiGraph<NodeWeight, EdgeWeight>,
+        from: NodeIndex,
+        to: NodeIndex,
+        passengers: u64,
+        max_duration: u64,
+        beam_width: usize,
+        h: &HashMap<NodeIndex, u64>,
+    ) -> Vec<Self> {
+        let heuristic = |node: NodeIndex| h.get(&node).copied().unwrap_or(0);
+
+        let mut frontier = vec![Frontier {
+            node: from,
+            edges: Vec::new(),
+            elapsed_duration: 0,
+            f: heuristic(from),
+        }];
+
+        let mut found = Vec::new();
+
+        while !frontier.is_empty() {
+            let mut successors = Vec::new();
+
+            for current in frontier.iter() {
+                if current.node == to {
+                    found.push(Self {
+                        edges: current.edges.clone(),
+                        duration: current.elapsed_duration,
+                        remaining_budget: max_duration.saturating_sub(current.elapsed_duration),
+                    });
+                    continue;
+                }
+
+                for edge_ref in graph.edges(current.node) {
+                    let edge_weight = edge_ref.weight();
+
+                    if edge_weight.get_remaining_capacity() < passengers {
+                        continue;
+                    }
+
+                    let elapsed_duration = current.elapsed_duration + edge_weight.get_duration();
+                    let f = elapsed_duration + heuristic(edge_ref.target());
+
+                    if f > max_duration {
+                        continue;
+                    }
+
+                    let mut edges = current.edges.clone();
+                    edges.push(edge_ref.id());
+
+                    successors.push(Frontier { node: edge_ref.target(), edges, elapsed_duration, f });
+                }
+            }
+
+            // keep only the top-`beam_width` partial paths ranked by f (lowest first)
+            successors.sort_unstable_by_key(|frontier_entry| frontier_entry.f);
+            successors.truncate(beam_width);
+
+            frontier = successors;
+        }
+
+        found
+    }
+}
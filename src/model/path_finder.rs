@@ -0,0 +1,715 @@
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::{BinaryHeap, HashMap},
+};
+
+use petgraph::graph::{DiGraph, EdgeIndex, NodeIndex};
+
+use super::{cost_profile::CostProfile, EdgeWeight, Model, NodeWeight};
+
+/// selectable path-finding strategy for `augment_group`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchStrategy {
+    /// the existing budget-limited recursive DFS enumerator
+    Dfs,
+    /// A* guided by the station-level minimum-travel-time heuristic, see `build_station_heuristic`
+    AStar,
+    /// beam search of the given width, guided by the same heuristic; trades completeness for a
+    /// bounded, predictable runtime on large scenarios
+    Beam(usize),
+}
+
+/// the station a node belongs to, if any (`NodeWeight::Default` has none)
+fn node_station(graph: &DiGraph<NodeWeight, EdgeWeight>, node_index: NodeIndex) -> Option<String> {
+    match graph.node_weight(node_index).unwrap() {
+        NodeWeight::Departure { station_id, .. } => Some(station_id.clone()),
+        NodeWeight::Arrival { station_id, .. } => Some(station_id.clone()),
+        NodeWeight::Transfer { station_id, .. } => Some(station_id.clone()),
+        NodeWeight::MainArrival { station_id } => Some(station_id.clone()),
+        NodeWeight::Default => None,
+    }
+}
+
+/// precomputes, for every station, an admissible lower bound on the minimum ride time to
+/// `target_station_id` by a backwards Dijkstra over a station-contracted graph: every `Ride` edge
+/// contributes its `duration`, every `Walk` (footpath) edge contributes 0, and all other edge types
+/// (board/alight/wait, which never cross a station boundary) are ignored. Because this relaxation
+/// can only ever underestimate the real travel time, it is safe to use as an A* heuristic.
+pub fn build_station_heuristic(graph: &DiGraph<NodeWeight, EdgeWeight>, target_station_id: &str) -> HashMap<String, u64> {
+    let mut incoming: HashMap<String, Vec<(String, u64)>> = HashMap::new();
+
+    for edge_index in graph.edge_indices() {
+        let weight = match graph.edge_weight(edge_index).unwrap() {
+            EdgeWeight::Ride { duration, .. } => Some(*duration),
+            EdgeWeight::Walk { .. } => Some(0),
+            _ => None,
+        };
+
+        let weight = match weight {
+            Some(weight) => weight,
+            None => continue,
+        };
+
+        let (from, to) = graph.edge_endpoints(edge_index).unwrap();
+
+        if let (Some(from_station), Some(to_station)) = (node_station(graph, from), node_station(graph, to)) {
+            if from_station != to_station {
+                incoming.entry(to_station).or_default().push((from_station, weight));
+            }
+        }
+    }
+
+    let mut h: HashMap<String, u64> = HashMap::new();
+    h.insert(target_station_id.to_string(), 0);
+
+    let mut heap = BinaryHeap::new();
+    heap.push(Reverse((0u64, target_station_id.to_string())));
+
+    while let Some(Reverse((cost, station))) = heap.pop() {
+        if let Some(&best) = h.get(&station) {
+            if best < cost {
+                continue;
+            }
+        }
+
+        if let Some(predecessors) = incoming.get(&station) {
+            for (from_station, weight) in predecessors {
+                let candidate = cost + weight;
+
+                let improved = match h.get(from_station) {
+                    Some(&existing) => candidate < existing,
+                    None => true,
+                };
+
+                if improved {
+                    h.insert(from_station.clone(), candidate);
+                    heap.push(Reverse((candidate, from_station.clone())));
+                }
+            }
+        }
+    }
+
+    h
+}
+
+/// frontier entry for `search_astar`'s heap, ordered so `BinaryHeap` pops the lowest `f` first
+struct AstarFrontier {
+    node: NodeIndex,
+    edges: Vec<EdgeIndex>,
+    g: u64,
+    f: u64,
+}
+
+impl PartialEq for AstarFrontier {
+    fn eq(&self, other: &Self) -> bool {
+        self.f == other.f
+    }
+}
+impl Eq for AstarFrontier {}
+
+impl PartialOrd for AstarFrontier {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for AstarFrontier {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.f.cmp(&self.f)
+    }
+}
+
+/// A* over the full time-expanded graph: `g` is elapsed time from the group's start, `f = g + h`
+/// using the station-level heuristic from `build_station_heuristic`. Stops at the first pop of
+/// `to`, which is then the earliest-arrival path - far faster than the budget-limited DFS and
+/// without its hand-tuned cost budget.
+pub fn search_astar(
+    graph: &DiGraph<NodeWeight, EdgeWeight>,
+    from: NodeIndex,
+    to: NodeIndex,
+    passengers: u64,
+    max_duration: u64,
+    h: &HashMap<String, u64>,
+) -> Option<(u64, Vec<EdgeIndex>)> {
+    let heuristic = |node_index: NodeIndex| node_station(graph, node_index).and_then(|station| h.get(&station).copied()).unwrap_or(0);
+
+    let mut heap = BinaryHeap::new();
+    heap.push(AstarFrontier { node: from, edges: Vec::new(), g: 0, f: heuristic(from) });
+
+    let mut best_g: HashMap<NodeIndex, u64> = HashMap::new();
+
+    while let Some(current) = heap.pop() {
+        if current.node == to {
+            return Some((max_duration.saturating_sub(current.g), current.edges));
+        }
+
+        if let Some(&seen) = best_g.get(&current.node) {
+            if seen <= current.g {
+                continue;
+            }
+        }
+        best_g.insert(current.node, current.g);
+
+        let mut walker = graph.neighbors(current.node).detach();
+
+        while let Some((edge_index, next_node_index)) = walker.next(graph) {
+            let edge_weight = graph.edge_weight(edge_index).unwrap();
+
+            if edge_weight.get_remaining_capacity() < passengers {
+                continue;
+            }
+
+            let g = current.g + edge_weight.get_duration();
+            if g > max_duration {
+                continue;
+            }
+
+            let mut edges = current.edges.clone();
+            edges.push(edge_index);
+
+            heap.push(AstarFrontier { node: next_node_index, edges, g, f: g + heuristic(next_node_index) });
+        }
+    }
+
+    None
+}
+
+/// beam search: expands the frontier one edge at a time, but after every expansion step keeps only
+/// the best `beam_width` partial paths ranked by `f = g + h` (the same station-level heuristic as
+/// `search_astar`) and discards the rest. Caps memory at O(beam_width * max_depth) at the cost of
+/// completeness; exhaustive DFS (`all_paths_dfs_recursive`) should be used instead when the caller
+/// needs every candidate path.
+pub fn beam_search(
+    graph: &DiGraph<NodeWeight, EdgeWeight>,
+    from: NodeIndex,
+    to: NodeIndex,
+    passengers: u64,
+    max_duration: u64,
+    beam_width: usize,
+    h: &HashMap<String, u64>,
+) -> Vec<(u64, Vec<EdgeIndex>)> {
+    let heuristic = |node_index: NodeIndex| node_station(graph, node_index).and_then(|station| h.get(&station).copied()).unwrap_or(0);
+
+    let mut frontier = vec![AstarFrontier { node: from, edges: Vec::new(), g: 0, f: heuristic(from) }];
+    let mut found = Vec::new();
+
+    while !frontier.is_empty() {
+        let mut successors = Vec::new();
+
+        for current in frontier.iter() {
+            if current.node == to {
+                found.push((max_duration.saturating_sub(current.g), current.edges.clone()));
+                continue;
+            }
+
+            let mut walker = graph.neighbors(current.node).detach();
+
+            while let Some((edge_index, next_node_index)) = walker.next(graph) {
+                let edge_weight = graph.edge_weight(edge_index).unwrap();
+
+                if edge_weight.get_remaining_capacity() < passengers {
+                    continue;
+                }
+
+                let g = current.g + edge_weight.get_duration();
+                let f = g + heuristic(next_node_index);
+
+                if f > max_duration {
+                    continue;
+                }
+
+                let mut edges = current.edges.clone();
+                edges.push(edge_index);
+
+                successors.push(AstarFrontier { node: next_node_index, edges, g, f });
+            }
+        }
+
+        // keep only the top-`beam_width` partial paths, ranked by f (lowest first)
+        successors.sort_unstable_by_key(|frontier_entry| frontier_entry.f);
+        successors.truncate(beam_width);
+
+        frontier = successors;
+    }
+
+    found
+}
+
+/// recursive DFS enumerator bounded by `max_duration` and a per-edge cost `budget`, used by
+/// `Model::find_solutions` to enumerate candidate paths for a group. Only traverses `Ride` edges
+/// that have enough remaining capacity for `passengers`, so every path returned here is
+/// immediately augmentable without over-booking.
+pub fn all_paths_dfs_recursive(
+    graph: &DiGraph<NodeWeight, EdgeWeight>,
+    from: NodeIndex,
+    to: NodeIndex,
+    passengers: u64,
+    max_duration: u64,
+    budget: u64,
+    profile: CostProfile,
+) -> Vec<(u64, Vec<EdgeIndex>)> {
+    let mut paths = Vec::new();
+    let mut edges = Vec::new();
+
+    dfs_step(graph, from, to, passengers, max_duration, budget, profile, &mut edges, 0, 0, &mut paths);
+
+    paths
+}
+
+fn dfs_step(
+    graph: &DiGraph<NodeWeight, EdgeWeight>,
+    current: NodeIndex,
+    to: NodeIndex,
+    passengers: u64,
+    max_duration: u64,
+    budget: u64,
+    profile: CostProfile,
+    edges: &mut Vec<EdgeIndex>,
+    duration: u64,
+    cost: u64,
+    paths: &mut Vec<(u64, Vec<EdgeIndex>)>,
+) {
+    if current == to {
+        paths.push((budget.saturating_sub(cost), edges.clone()));
+        return;
+    }
+
+    let mut walker = graph.neighbors(current).detach();
+
+    while let Some((edge_index, next_node_index)) = walker.next(graph) {
+        let edge_weight = graph.edge_weight(edge_index).unwrap();
+
+        if edge_weight.get_remaining_capacity() < passengers {
+            continue;
+        }
+
+        let next_duration = duration + edge_weight.get_duration();
+        let next_cost = cost + profile.cost(edge_weight);
+
+        if next_duration > max_duration || next_cost > budget {
+            continue;
+        }
+
+        edges.push(edge_index);
+        dfs_step(graph, next_node_index, to, passengers, max_duration, budget, profile, edges, next_duration, next_cost, paths);
+        edges.pop();
+    }
+}
+
+/// bottleneck remaining capacity along `path` (the least remaining capacity among its edges)
+pub fn bottleneck_remaining_capacity(graph: &DiGraph<NodeWeight, EdgeWeight>, path: &[EdgeIndex]) -> u64 {
+    path.iter()
+        .map(|edge_index| graph.edge_weight(*edge_index).unwrap().get_remaining_capacity())
+        .min()
+        .unwrap_or(u64::MAX)
+}
+
+/// upper bound on a group's `via_stations` for `search_route_with_waypoints`: trying every
+/// ordering is factorial in the via-set size, so this caps the search at a still-instant 720
+/// orderings
+pub const MAX_VIA_STATIONS: usize = 6;
+
+/// every permutation of `items`, generated iteratively via Heap's algorithm
+fn permutations<T: Clone>(items: &[T]) -> Vec<Vec<T>> {
+    let n = items.len();
+    let mut items = items.to_vec();
+    let mut result = vec![items.clone()];
+
+    if n == 0 {
+        return result;
+    }
+
+    let mut c = vec![0usize; n];
+    let mut i = 0;
+
+    while i < n {
+        if c[i] < i {
+            if i % 2 == 0 {
+                items.swap(0, i);
+            } else {
+                items.swap(c[i], i);
+            }
+
+            result.push(items.clone());
+            c[i] += 1;
+            i = 0;
+        } else {
+            c[i] = 0;
+            i += 1;
+        }
+    }
+
+    result
+}
+
+/// routes a group through every one of its mandatory `via_stations`, trying every visiting order
+/// (Heap's algorithm, see `permutations`) and chaining the existing A* earliest-arrival search
+/// (`search_astar`) leg by leg - start -> via[0] -> via[1] -> ... -> destination - using each leg's
+/// arrival time as the next leg's departure time. Short-circuits an ordering as soon as its partial
+/// duration already exceeds `max_duration`, and keeps only the cheapest (lowest total duration)
+/// complete ordering found. Only practical for a small via-set since cost grows factorially with
+/// `via_stations.len()`, so a via-set longer than `MAX_VIA_STATIONS` is silently capped to its first
+/// `MAX_VIA_STATIONS` entries rather than permuting (or panicking on) the full set - a malformed CSV
+/// row with an oversized via-set shouldn't be able to crash the whole parallel solve. The
+/// `build_station_heuristic` table for each distinct waypoint (destination + every via-station) is
+/// built once before the permutation loop, since the heuristic doesn't depend on visiting order -
+/// rebuilding it per leg per ordering would mean up to 720 orderings x 7 legs of full-graph reverse
+/// Dijkstra for a single 6-via-station group. Read-only, like `rank_candidates`, so it is safe to
+/// call from a parallel candidate-generation phase.
+pub fn search_route_with_waypoints(
+    model: &Model,
+    start_station: &str,
+    via_stations: &[String],
+    destination_station: &str,
+    departure_time: u64,
+    passengers: u64,
+    max_duration: u64,
+) -> Option<(u64, Vec<EdgeIndex>)> {
+    let via_stations = &via_stations[..via_stations.len().min(MAX_VIA_STATIONS)];
+
+    // the heuristic for a given waypoint only depends on that waypoint, never on the order it's
+    // visited in, so precompute one table per distinct waypoint (destination + each via-station)
+    // here, outside the permutation loop - a 6-via-station group has up to 720 orderings x 7 legs,
+    // and rebuilding a full-graph reverse Dijkstra per leg per ordering defeated the whole point of
+    // the A*/beam-search line of work this function leans on
+    let heuristics: HashMap<&str, HashMap<String, u64>> = via_stations
+        .iter()
+        .map(String::as_str)
+        .chain(std::iter::once(destination_station))
+        .map(|waypoint| (waypoint, build_station_heuristic(&model.graph, waypoint)))
+        .collect();
+
+    let mut best: Option<(u64, Vec<EdgeIndex>)> = None;
+
+    for ordering in permutations(via_stations) {
+        let waypoints: Vec<&str> = ordering.iter().map(String::as_str).chain(std::iter::once(destination_station)).collect();
+
+        let mut current_station = start_station;
+        let mut current_time = departure_time;
+        let mut total_duration = 0u64;
+        let mut edges = Vec::new();
+        let mut feasible = true;
+
+        for waypoint in waypoints {
+            let from = match model.find_start_node_index(current_station, current_time) {
+                Some(node_index) => node_index,
+                None => { feasible = false; break; }
+            };
+            let to = match model.find_end_node_index(waypoint) {
+                Some(node_index) => node_index,
+                None => { feasible = false; break; }
+            };
+
+            let remaining_budget = max_duration.saturating_sub(total_duration);
+            let h = &heuristics[waypoint];
+
+            let leg = match search_astar(&model.graph, from, to, passengers, remaining_budget, h) {
+                Some(leg) => leg,
+                None => { feasible = false; break; }
+            };
+
+            let leg_duration = remaining_budget - leg.0;
+            total_duration += leg_duration;
+
+            if total_duration > max_duration {
+                feasible = false;
+                break;
+            }
+
+            current_time += leg_duration;
+            current_station = waypoint;
+            edges.extend(leg.1);
+        }
+
+        if feasible {
+            let remaining_total = max_duration - total_duration;
+
+            if best.as_ref().map_or(true, |(best_remaining, _)| remaining_total > *best_remaining) {
+                best = Some((remaining_total, edges));
+            }
+        }
+    }
+
+    best
+}
+
+/// computes a group's ranked candidate paths against a read-only graph snapshot, without touching
+/// `utilization`. This is the parallel-generation half of the two-phase scheme used by
+/// `Model::find_solutions`: many groups' candidates can be ranked concurrently via `rayon::par_iter`
+/// since nothing here is mutated, and a sequential commit phase decides afterwards which candidate
+/// each group actually gets. Candidates are searched capacity-obliviously (as if `passengers == 1`);
+/// the commit phase re-checks `bottleneck_remaining_capacity` against the graph as it stands at
+/// commit time, since other groups may have consumed capacity on shared edges since ranking.
+/// Cheapest (highest remaining budget/duration slack) first.
+pub fn rank_candidates(
+    graph: &DiGraph<NodeWeight, EdgeWeight>,
+    from: NodeIndex,
+    to: NodeIndex,
+    max_duration: u64,
+    budget: u64,
+    strategy: SearchStrategy,
+    station_heuristic: Option<&HashMap<String, u64>>,
+    profile: CostProfile,
+) -> Vec<(u64, Vec<EdgeIndex>)> {
+    let mut candidates = match strategy {
+        SearchStrategy::Dfs => all_paths_dfs_recursive(graph, from, to, 1, max_duration, budget, profile),
+        SearchStrategy::AStar => {
+            let h = station_heuristic.expect("station heuristic required for SearchStrategy::AStar");
+            search_astar(graph, from, to, 1, max_duration, h).into_iter().collect()
+        }
+        SearchStrategy::Beam(beam_width) => {
+            let h = station_heuristic.expect("station heuristic required for SearchStrategy::Beam");
+            beam_search(graph, from, to, 1, max_duration, beam_width, h)
+        }
+    };
+
+    candidates.sort_unstable_by_key(|(remaining_budget, _)| *remaining_budget);
+    candidates.reverse();
+
+    candidates
+}
+
+/// a real min-cost-flow-style commodity loop for a single group: repeatedly finds the cheapest
+/// capacity-feasible path under `max_duration`, assigns `min(remaining, bottleneck)` passengers to
+/// it and augments every `Ride` edge of that path, then loops on the leftover passengers until
+/// zero are left or no feasible path remains. Returns `(passengers_placed, passengers_spilled)`.
+pub fn augment_group(
+    graph: &mut DiGraph<NodeWeight, EdgeWeight>,
+    from: NodeIndex,
+    to: NodeIndex,
+    passengers: u64,
+    max_duration: u64,
+    budget: u64,
+    strategy: SearchStrategy,
+    station_heuristic: Option<&HashMap<String, u64>>,
+    profile: CostProfile,
+) -> (u64, u64) {
+    let mut remaining = passengers;
+
+    while remaining > 0 {
+        let path = match strategy {
+            SearchStrategy::Dfs => {
+                // any capacity >= 1 is a feasible candidate - the actual placeable amount is
+                // capped afterwards by the path's bottleneck remaining capacity
+                let mut candidates = all_paths_dfs_recursive(graph, from, to, 1, max_duration, budget, profile);
+
+                if candidates.is_empty() {
+                    break;
+                }
+
+                // cheapest (highest remaining budget) path first
+                candidates.sort_unstable_by_key(|(remaining_budget, _)| *remaining_budget);
+                candidates.reverse();
+
+                candidates.into_iter().next().unwrap().1
+            }
+            SearchStrategy::AStar => {
+                let h = station_heuristic.expect("station heuristic required for SearchStrategy::AStar");
+
+                match search_astar(graph, from, to, 1, max_duration, h) {
+                    Some((_, path)) => path,
+                    None => break,
+                }
+            }
+            SearchStrategy::Beam(beam_width) => {
+                let h = station_heuristic.expect("station heuristic required for SearchStrategy::Beam");
+
+                let mut candidates = beam_search(graph, from, to, 1, max_duration, beam_width, h);
+
+                if candidates.is_empty() {
+                    break;
+                }
+
+                candidates.sort_unstable_by_key(|(remaining_budget, _)| *remaining_budget);
+                candidates.reverse();
+
+                candidates.into_iter().next().unwrap().1
+            }
+        };
+
+        let bottleneck = bottleneck_remaining_capacity(graph, &path);
+
+        if bottleneck == 0 {
+            break;
+        }
+
+        let placed = remaining.min(bottleneck);
+
+        for edge_index in path.iter() {
+            graph.edge_weight_mut(*edge_index).unwrap().increase_utilization(placed);
+        }
+
+        remaining -= placed;
+    }
+
+    (passengers - remaining, remaining)
+}
+
+#[cfg(test)]
+mod search_route_with_waypoints_tests {
+    use std::collections::HashMap;
+
+    use petgraph::graph::DiGraph;
+
+    use super::*;
+
+    /// builds a tiny two-leg timetable A -(ride)-> V -(ride)-> D, with the transfer/board/alight
+    /// scaffolding `search_route_with_waypoints` relies on via `find_start_node_index`/
+    /// `find_end_node_index`, so the via-station "V" can be routed through on the way to "D"
+    fn two_leg_model() -> Model {
+        let mut graph = DiGraph::new();
+
+        let transfer_a = graph.add_node(NodeWeight::Transfer { time: 0, station_id: "A".into() });
+        let departure_a = graph.add_node(NodeWeight::Departure { trip_id: 1, time: 0, station_id: "A".into() });
+        let arrival_v = graph.add_node(NodeWeight::Arrival { trip_id: 1, time: 50, station_id: "V".into() });
+        let main_arrival_v = graph.add_node(NodeWeight::MainArrival { station_id: "V".into() });
+        let transfer_v = graph.add_node(NodeWeight::Transfer { time: 50, station_id: "V".into() });
+        let departure_v = graph.add_node(NodeWeight::Departure { trip_id: 2, time: 60, station_id: "V".into() });
+        let arrival_d = graph.add_node(NodeWeight::Arrival { trip_id: 2, time: 100, station_id: "D".into() });
+        let main_arrival_d = graph.add_node(NodeWeight::MainArrival { station_id: "D".into() });
+
+        graph.add_edge(transfer_a, departure_a, EdgeWeight::Board);
+        graph.add_edge(departure_a, arrival_v, EdgeWeight::Ride { duration: 50, capacity: 10, utilization: 0 });
+        graph.add_edge(arrival_v, main_arrival_v, EdgeWeight::MainArrivalRelation);
+        graph.add_edge(arrival_v, transfer_v, EdgeWeight::Alight { duration: 0 });
+        graph.add_edge(transfer_v, departure_v, EdgeWeight::Board);
+        graph.add_edge(departure_v, arrival_d, EdgeWeight::Ride { duration: 40, capacity: 10, utilization: 0 });
+        graph.add_edge(arrival_d, main_arrival_d, EdgeWeight::MainArrivalRelation);
+
+        let mut stations_departures = HashMap::new();
+        stations_departures.insert("A".to_string(), vec![(0u64, transfer_a)]);
+        stations_departures.insert("V".to_string(), vec![(50u64, transfer_v)]);
+
+        let mut station_arrival_main_node_indices = HashMap::new();
+        station_arrival_main_node_indices.insert("V".to_string(), main_arrival_v);
+        station_arrival_main_node_indices.insert("D".to_string(), main_arrival_d);
+
+        Model { graph, stations_departures, station_arrival_main_node_indices }
+    }
+
+    /// the precomputed-heuristic rewrite must still find the same route through the mandatory
+    /// via-station as before: a feasible ride all the way to the final destination
+    #[test]
+    fn routes_through_a_single_via_station_to_the_destination() {
+        let model = two_leg_model();
+
+        let result = search_route_with_waypoints(&model, "A", &["V".to_string()], "D", 0, 1, 1000);
+
+        let (_, edges) = result.expect("a feasible route through V to D should be found");
+        assert_eq!(edges.len(), 6); // Board, Ride and MainArrivalRelation for each leg, A->V and V->D
+    }
+}
+
+#[cfg(test)]
+mod search_astar_tests {
+    use super::*;
+
+    fn ride(duration: u64, capacity: u64) -> EdgeWeight {
+        EdgeWeight::Ride { duration, capacity, utilization: 0 }
+    }
+
+    /// between a direct expensive-looking-but-short detour and a longer hop count, A* must still
+    /// surface the globally fastest path, not just the fewest edges
+    #[test]
+    fn finds_the_earliest_arrival_path_over_a_longer_detour() {
+        let mut graph = DiGraph::new();
+
+        let start = graph.add_node(NodeWeight::Default);
+        let via = graph.add_node(NodeWeight::Default);
+        let destination = graph.add_node(NodeWeight::Default);
+
+        let direct_edge = graph.add_edge(start, destination, ride(20, 10));
+        let leg_a = graph.add_edge(start, via, ride(5, 10));
+        let leg_b = graph.add_edge(via, destination, ride(5, 10));
+
+        let h = HashMap::new(); // no station heuristic needed: nodes here have no station
+
+        let (remaining, edges) = search_astar(&graph, start, destination, 1, 100, &h).expect("a path should be found");
+
+        assert_eq!(remaining, 90); // 100 - (5 + 5), the two-hop path, not the 20-duration direct edge
+        assert_eq!(edges, vec![leg_a, leg_b]);
+        assert!(!edges.contains(&direct_edge));
+    }
+
+    /// a path whose only route exceeds `max_duration` must be rejected rather than returned anyway
+    #[test]
+    fn returns_none_when_every_path_exceeds_max_duration() {
+        let mut graph = DiGraph::new();
+
+        let start = graph.add_node(NodeWeight::Default);
+        let destination = graph.add_node(NodeWeight::Default);
+
+        graph.add_edge(start, destination, ride(50, 10));
+
+        let h = HashMap::new();
+
+        assert!(search_astar(&graph, start, destination, 1, 10, &h).is_none());
+    }
+
+    /// an edge with no remaining capacity for the requested passenger count must be skipped, even
+    /// though it would otherwise be the only route
+    #[test]
+    fn skips_edges_without_enough_remaining_capacity() {
+        let mut graph = DiGraph::new();
+
+        let start = graph.add_node(NodeWeight::Default);
+        let destination = graph.add_node(NodeWeight::Default);
+
+        graph.add_edge(start, destination, EdgeWeight::Ride { duration: 10, capacity: 5, utilization: 5 });
+
+        let h = HashMap::new();
+
+        assert!(search_astar(&graph, start, destination, 1, 100, &h).is_none());
+    }
+}
+
+#[cfg(test)]
+mod beam_search_tests {
+    use super::*;
+
+    fn ride(duration: u64, capacity: u64) -> EdgeWeight {
+        EdgeWeight::Ride { duration, capacity, utilization: 0 }
+    }
+
+    /// with a beam wide enough to keep every partial path alive, beam search must still find the
+    /// destination and report it among its results, same as an unbounded search would
+    #[test]
+    fn finds_the_destination_when_the_beam_is_wide_enough() {
+        let mut graph = DiGraph::new();
+
+        let start = graph.add_node(NodeWeight::Default);
+        let via = graph.add_node(NodeWeight::Default);
+        let destination = graph.add_node(NodeWeight::Default);
+
+        graph.add_edge(start, via, ride(5, 10));
+        graph.add_edge(via, destination, ride(5, 10));
+
+        let h = HashMap::new();
+
+        let found = beam_search(&graph, start, destination, 1, 100, 10, &h);
+
+        assert_eq!(found, vec![(90, vec![graph.find_edge(start, via).unwrap(), graph.find_edge(via, destination).unwrap()])]);
+    }
+
+    /// a beam width of 1 that gets forced onto the worse of two branches at the first expansion
+    /// step must discard the better branch entirely, not fall back to it - this is the whole
+    /// completeness-for-speed tradeoff beam search makes over search_astar
+    #[test]
+    fn narrow_beam_can_discard_the_only_feasible_branch() {
+        let mut graph = DiGraph::new();
+
+        let start = graph.add_node(NodeWeight::Default);
+        let dead_end = graph.add_node(NodeWeight::Default);
+        let via = graph.add_node(NodeWeight::Default);
+        let destination = graph.add_node(NodeWeight::Default);
+
+        // dead_end looks cheaper one edge in (duration 1 vs 5) but never reaches destination
+        graph.add_edge(start, dead_end, ride(1, 10));
+        graph.add_edge(start, via, ride(5, 10));
+        graph.add_edge(via, destination, ride(5, 10));
+
+        let h = HashMap::new();
+
+        assert!(beam_search(&graph, start, destination, 1, 100, 1, &h).is_empty());
+    }
+}
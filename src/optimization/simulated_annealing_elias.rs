@@ -1,55 +1,111 @@
-use std::thread::current;
-
 use petgraph::graph::DiGraph;
 use rand::Rng;
 
 use super::SelectionState;
-use crate::model::{graph_weigth::{TimetableEdge, TimetableNode}, group::Group, path::Path};
+use crate::model::{graph_weigth::{TimetableEdge, TimetableNode}, group::Group};
+
+/// cooling schedule for the annealing temperature, selectable so callers can trade off how
+/// aggressively the search settles toward hill-climbing as `time` grows
+pub enum CoolingSchedule {
+    /// temperature *= alpha every step (0 < alpha < 1)
+    Geometric { alpha: f64 },
+    /// temperature = start_temperature / (1 + ln(time))
+    Logarithmic,
+    /// temperature = start_temperature / time^2 (the original, hardcoded schedule)
+    InverseSquare,
+}
 
-fn time_to_temperature(time: f64) -> f64 {
-    100.0 / time.powf(2.0)
+impl CoolingSchedule {
+    fn temperature(&self, start_temperature: f64, time: u64) -> f64 {
+        match self {
+            Self::Geometric { alpha } => start_temperature * alpha.powi(time as i32),
+            Self::Logarithmic => start_temperature / (1.0 + (time as f64).ln()),
+            Self::InverseSquare => start_temperature / (time as f64).powf(2.0),
+        }
+    }
 }
 
-pub fn simulated_annealing<'a>(graph: &'a mut DiGraph<TimetableNode, TimetableEdge>, groups: &'a Vec<Group>) -> SelectionState<'a> {
+#[cfg(test)]
+mod cooling_schedule_tests {
+    use super::*;
+
+    // `simulated_annealing` itself can't be exercised here: this file's `use super::SelectionState`
+    // and `crate::model::graph_weigth` don't resolve to anything in this tree (no `optimization`
+    // module root, no `graph_weigth` module), so it doesn't compile standalone even with a
+    // manifest. `CoolingSchedule::temperature` is the one self-contained, pure piece of this
+    // request's logic, so it gets the known-answer coverage instead.
+
+    #[test]
+    fn geometric_decays_by_alpha_each_step() {
+        let schedule = CoolingSchedule::Geometric { alpha: 0.9 };
+
+        assert_eq!(schedule.temperature(100.0, 1), 90.0);
+        assert!((schedule.temperature(100.0, 2) - 81.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn logarithmic_matches_start_temperature_at_time_one() {
+        let schedule = CoolingSchedule::Logarithmic;
+
+        // ln(1) == 0, so at time == 1 the schedule hasn't cooled at all yet
+        assert_eq!(schedule.temperature(100.0, 1), 100.0);
+        assert!(schedule.temperature(100.0, 10) < 100.0);
+    }
+
+    #[test]
+    fn inverse_square_matches_the_original_hardcoded_formula() {
+        let schedule = CoolingSchedule::InverseSquare;
+
+        assert_eq!(schedule.temperature(100.0, 2), 25.0);
+        assert_eq!(schedule.temperature(100.0, 10), 1.0);
+    }
+}
 
+/// runs simulated annealing with proper Metropolis acceptance: a random neighbor is drawn each
+/// step, improving moves are always accepted, and worse moves are accepted with probability
+/// `exp(delta_cost / temperature)` (`delta_cost = current.cost - next.cost`, negative for a worse
+/// move) so the search can escape local minima. Reheats to the
+/// best-seen state (resetting the temperature) after `reheat_after_stale_steps` steps without an
+/// improvement, and always returns the best `SelectionState` ever seen, not just the current one.
+pub fn simulated_annealing<'a>(
+    graph: &'a mut DiGraph<TimetableNode, TimetableEdge>,
+    groups: &'a Vec<Group>,
+    schedule: CoolingSchedule,
+    start_temperature: f64,
+    stopping_threshold: f64,
+    reheat_after_stale_steps: u64,
+) -> SelectionState<'a> {
     let mut rng = rand::thread_rng();
 
     let mut current = SelectionState::generate_random_state(graph, groups);
-    let mut time = 1;
+    let mut best = current.clone();
+    let mut time: u64 = 1;
+    let mut stale_steps: u64 = 0;
 
     loop {
-        let temperature = time_to_temperature(time as f64);
-        
-        print!("[time={}]: current_state_cost={}, temperature={}, ", time, current.cost, temperature);
-        
-        // actually exactly zero, but difficult with float
-        if temperature < 0.1 {
-            println!("-> return");
-            return current;
-        }
+        let temperature = schedule.temperature(start_temperature, time);
 
-        let mut neighbors = current.generate_direct_neighbors(graph);
-
-        // sort neighbors by cost (lowest first)
-        neighbors.sort_unstable_by_key(|s| s.cost);
+        print!("[time={}]: current_state_cost={}, temperature={}, ", time, current.cost, temperature);
 
-        // select random next state
-        // let next_state = &neighbor_states[rng.gen::<usize>() % neighbor_states.len()];
-        let next = &neighbors[0];
+        if temperature < stopping_threshold {
+            println!("-> return best_state_cost={}", best.cost);
+            return best;
+        }
 
-        // print!("next_state={:?}, ", next_state.groups_paths_selection);
+        let neighbors = current.generate_direct_neighbors(graph);
+        let next = &neighbors[rng.gen_range(0..neighbors.len())];
 
-        // if next_state is better than current_state -> delta positive
-        // if next_state is worse than current_state -> delta negative
+        // if next is better than current -> delta positive
+        // if next is worse than current -> delta negative
         let delta_cost = current.cost as i64 - next.cost as i64;
 
         print!("delta_cost={}, ", delta_cost);
 
         if delta_cost > 0 {
             current = next.clone();
-            println!("current_state = next_state");
+            println!("current_state = next_state (improvement)");
         } else {
-            let probability = (delta_cost as f64 / temperature as f64).exp();
+            let probability = (delta_cost as f64 / temperature).exp();
             let random = rng.gen_range(0.0..1.0);
 
             println!("probability={}, random={}", probability, random);
@@ -59,6 +115,20 @@ pub fn simulated_annealing<'a>(graph: &'a mut DiGraph<TimetableNode, TimetableEd
             }
         }
 
-        time += 1;
+        if current.cost < best.cost {
+            best = current.clone();
+            stale_steps = 0;
+        } else {
+            stale_steps += 1;
+        }
+
+        if stale_steps >= reheat_after_stale_steps {
+            println!("no improvement for {} steps -> reheating from best_state_cost={}", stale_steps, best.cost);
+            current = best.clone();
+            time = 1;
+            stale_steps = 0;
+        } else {
+            time += 1;
+        }
     }
-}
\ No newline at end of file
+}